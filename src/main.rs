@@ -9,6 +9,7 @@ use camino::{Utf8Path, Utf8PathBuf};
 use clap::{ArgAction, Parser};
 use log::*;
 use memmap2::Mmap;
+use rayon::prelude::*;
 
 #[derive(Debug, Parser)]
 struct Args {
@@ -19,6 +20,25 @@ struct Args {
     #[clap(short='p', long)]
     perspective_correct: bool,
 
+    /// Emit sprites and faces as true-color RGBA PNGs (real alpha) instead of
+    /// indexed PNGs with a transparent palette slot. Composite textures are
+    /// always written as indexed PNGs.
+    #[clap(long)]
+    rgba: bool,
+
+    /// Shade sprites using COLORMAP light level LEVEL (0-31 dark, 32 is the
+    /// invulnerability map)
+    #[clap(long, value_name = "LEVEL")]
+    colormap: Option<u8>,
+
+    /// Emit a sprite per COLORMAP level instead of just one
+    #[clap(long)]
+    all_colormaps: bool,
+
+    /// Extract DMX digital sound effect lumps (DS*) to WAV
+    #[clap(long)]
+    sounds: bool,
+
     /// Verbosity (-v, -vv, -vvv, etc.)
     #[clap(short, long, action(ArgAction::Count))]
     verbose: u8,
@@ -63,6 +83,11 @@ fn doomstr(d: &[u8]) -> &str {
 // to rediscover this at runtime
 const TRANSPARENT: u8 = 251;
 
+// https://doomwiki.org/wiki/COLORMAP
+// 34 maps of 256 bytes each: 0-31 are progressively darker light levels,
+// 32 is the (inverted) invulnerability map, 33 is unused.
+const COLORMAP_LEVELS: u8 = 34;
+
 fn go(args: Args) -> Result<()> {
     let wad = map_wad(&args.wad)?;
 
@@ -85,26 +110,95 @@ fn go(args: Args) -> Result<()> {
         .find(|l| l.name() == "PLAYPAL")
         .expect("No palette");
     let palette = read_palette(&wad, palette);
+    let rgb_table: Vec<[u8; 3]> = palette.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect();
 
-    let used_colors: &mut [bool] = &mut [false; 256];
+    let colormap_levels: Vec<u8> = if args.all_colormaps {
+        (0..COLORMAP_LEVELS).collect()
+    } else if let Some(level) = args.colormap {
+        if level >= COLORMAP_LEVELS {
+            bail!("--colormap level must be 0-{}, got {level}", COLORMAP_LEVELS - 1);
+        }
+        vec![level]
+    } else {
+        Vec::new()
+    };
 
-    let sprites = dir
+    let colormap: &[u8] = if !colormap_levels.is_empty() {
+        let lump = dir
+            .iter()
+            .find(|l| l.name() == "COLORMAP")
+            .expect("No colormap");
+        read_colormap(&wad, lump)
+    } else {
+        &[]
+    };
+
+    let sprites: Vec<&Filelump> = dir
         .iter()
         .skip_while(|l| l.name() != "S_START")
         .skip(1)
-        .take_while(|l| l.name() != "S_END");
+        .take_while(|l| l.name() != "S_END")
+        .collect();
+    let faces: Vec<&Filelump> = dir.iter().filter(|l| l.name().starts_with("STF")).collect();
+
+    let decode_sprite = |l: &Filelump| {
+        save_sprite(
+            &wad,
+            l,
+            args.perspective_correct,
+            args.rgba,
+            palette,
+            &rgb_table,
+            colormap,
+            &colormap_levels,
+        )
+    };
 
     info!("Sprites:");
-    for s in sprites {
-        info!("  {}", s.name());
-        save_sprite(&wad, s, args.perspective_correct, palette, used_colors)?;
-    }
+    let sprite_colors: Vec<[bool; 256]> = sprites
+        .par_iter()
+        .map(|&l| {
+            info!("  {}", l.name());
+            decode_sprite(l)
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    let faces = dir.iter().filter(|l| l.name().starts_with("STF"));
     info!("Faces:");
-    for f in faces {
-        info!("  {}", f.name());
-        save_sprite(&wad, f, args.perspective_correct, palette, used_colors)?;
+    let face_colors: Vec<[bool; 256]> = faces
+        .par_iter()
+        .map(|&l| {
+            info!("  {}", l.name());
+            decode_sprite(l)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut used_colors = [false; 256];
+    for lump_colors in sprite_colors.iter().chain(&face_colors) {
+        for (used, &was_used) in used_colors.iter_mut().zip(lump_colors) {
+            *used |= was_used;
+        }
+    }
+
+    if args.sounds {
+        let sounds = dir.iter().filter(|l| l.name().starts_with("DS"));
+        info!("Sounds:");
+        for snd in sounds {
+            info!("  {}", snd.name());
+            save_sound(&wad, snd)?;
+        }
+    }
+
+    let texture_colors = save_textures(&wad, &dir, palette)?;
+
+    // --rgba doesn't rely on an unused palette slot for transparency, so
+    // sprites/faces don't need to be folded in here when it's set. Composite
+    // textures are always indexed regardless of --rgba though, so they
+    // always rely on the TRANSPARENT sentinel and are always folded in.
+    if args.rgba {
+        used_colors = [false; 256];
+    }
+    for (used, was_used) in used_colors.iter_mut().zip(texture_colors) {
+        *used |= was_used;
     }
 
     // We can use these for transparency
@@ -149,13 +243,20 @@ fn read_palette<'a>(wad: &'a [u8], lump: &Filelump) -> &'a [u8] {
     &wad[start..end]
 }
 
+fn read_colormap<'a>(wad: &'a [u8], lump: &Filelump) -> &'a [u8] {
+    let len = COLORMAP_LEVELS as usize * 256;
+    let start = lump.filepos as usize;
+    let end = start + len;
+    &wad[start..end]
+}
+
 // https://doomwiki.org/wiki/Picture_format
 #[derive(BinRead, Debug)]
 struct PatchHeader {
     width: u16,
     height: u16,
-    _leftoffset: i16,
-    _topoffset: i16,
+    leftoffset: i16,
+    topoffset: i16,
     #[br(count = width)]
     columnofs: Vec<u32>,
 }
@@ -167,14 +268,23 @@ struct PostHeader {
     length: u8,
 }
 
-fn save_sprite(
-    wad: &[u8],
-    sprite: &Filelump,
-    upsample: bool,
-    palette: &[u8],
-    used_colors: &mut [bool],
-) -> Result<()> {
-    let base = sprite.filepos as u64;
+// A decoded patch: its dimensions, palette indexes, a parallel mask of
+// which pixels were actually painted by a post vs. left as background, and
+// the grab-point offset used as a sprite's hotspot (see `grAb` in
+// `save_indexed_png`/`save_rgba_png`).
+struct DecodedPatch {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    painted: Vec<bool>,
+    left_offset: i32,
+    top_offset: i32,
+}
+
+// Doom images are column major, with each column containing "posts"
+// of pixels with a starting y coordinate. Transparent parts are skipped.
+fn decode_patch(wad: &[u8], filepos: u32) -> Result<DecodedPatch> {
+    let base = filepos as u64;
     let mut c = Cursor::new(wad);
     let c = &mut c;
     c.seek(SeekFrom::Start(base))?;
@@ -183,12 +293,17 @@ fn save_sprite(
     trace!("    {header:?}");
 
     let mut pixels = vec![TRANSPARENT; header.width as usize * header.height as usize];
+    let mut painted = vec![false; header.width as usize * header.height as usize];
 
-    // Doom images are column major, with each column containing "posts"
-    // of pixels with a starting y coordinate. Transparent parts are skipped.
     for (x, col) in header.columnofs.iter().enumerate() {
         // trace!("      column {x}:");
         c.seek(SeekFrom::Start(base + *col as u64))?;
+        // DeePsea tall-patch convention: a topdelta no greater than the
+        // previous one means "relative to the previous post's top" rather
+        // than "absolute from the column's start", which is how posts can
+        // address rows past the classic 254-row cap.
+        let mut last_topdelta: i32 = -1;
+        let mut abs_top: i32 = 0;
         loop {
             // trace!("At {}", c.position());
             let post: PostHeader = c.read_le()?;
@@ -196,58 +311,217 @@ fn save_sprite(
                 // trace!("        EOC");
                 break;
             }
+            let topdelta = post.topdelta as i32;
+            abs_top = if topdelta <= last_topdelta {
+                abs_top + topdelta
+            } else {
+                topdelta
+            };
+            last_topdelta = topdelta;
             /*
             trace!(
                 "        [{}..{}]",
-                post.topdelta,
-                post.topdelta as u32 + post.length as u32
+                abs_top,
+                abs_top + post.length as i32
             );
             */
             for dy in 0..post.length {
                 let px = read_u8(c)?;
-                // trace!("          [{}] = {px}", post.topdelta + dy);
-                used_colors[px as usize] = true;
-                pixels[x + (post.topdelta + dy) as usize * header.width as usize] = px;
+                // trace!("          [{}] = {px}", abs_top + dy as i32);
+                let y = abs_top + dy as i32;
+                if y >= 0 && (y as usize) < header.height as usize {
+                    let i = x + y as usize * header.width as usize;
+                    pixels[i] = px;
+                    painted[i] = true;
+                }
             }
             let _pad = read_u8(c)?;
         }
     }
 
+    Ok(DecodedPatch {
+        width: header.width as u32,
+        height: header.height as u32,
+        pixels,
+        painted,
+        left_offset: header.leftoffset as i32,
+        top_offset: header.topoffset as i32,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_sprite(
+    wad: &[u8],
+    sprite: &Filelump,
+    upsample: bool,
+    rgba: bool,
+    palette: &[u8],
+    rgb_table: &[[u8; 3]],
+    colormap: &[u8],
+    colormap_levels: &[u8],
+) -> Result<[bool; 256]> {
+    let DecodedPatch {
+        mut width,
+        mut height,
+        mut pixels,
+        mut painted,
+        mut left_offset,
+        mut top_offset,
+    } = decode_patch(wad, sprite.filepos)?;
+
+    let mut used_colors = [false; 256];
+    for (&px, &is_painted) in pixels.iter().zip(&painted) {
+        if is_painted {
+            used_colors[px as usize] = true;
+        }
+    }
+
     let outname = sprite.name().to_owned() + ".png";
 
-    let mut width = header.width as u32;
-    let mut height = header.height as u32;
     if upsample {
         // Do a dumb nearest-neighbor upscale at a 5:6 ratio to match the
         // pixel aspect ratio Doom ran on.
+        let srcwidth = width as usize;
+        let srcheight = height as usize;
         width *= 5;
         height *= 6;
+        pixels = upscale_5x6(&pixels, srcwidth, srcheight);
+        painted = upscale_5x6(&painted, srcwidth, srcheight);
+        left_offset *= 5;
+        top_offset *= 6;
+    }
+    let offsets = Some((left_offset, top_offset));
+
+    if colormap_levels.is_empty() {
+        encode_sprite(
+            &outname, width, height, &pixels, &painted, rgba, palette, rgb_table, offsets,
+        )?;
+        return Ok(used_colors);
+    }
+
+    for &level in colormap_levels {
+        let shaded = apply_colormap(&pixels, &painted, colormap, level);
+        let name = if colormap_levels.len() > 1 {
+            format!("{}_{level}.png", sprite.name())
+        } else {
+            outname.clone()
+        };
+        encode_sprite(
+            &name, width, height, &shaded, &painted, rgba, palette, rgb_table, offsets,
+        )?;
+    }
+
+    Ok(used_colors)
+}
+
+fn apply_colormap(pixels: &[u8], painted: &[bool], colormap: &[u8], level: u8) -> Vec<u8> {
+    let map = &colormap[level as usize * 256..];
+    pixels
+        .iter()
+        .zip(painted)
+        .map(|(&p, &is_painted)| {
+            if is_painted {
+                map[p as usize]
+            } else {
+                TRANSPARENT
+            }
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn encode_sprite(
+    outname: &str,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    painted: &[bool],
+    rgba: bool,
+    palette: &[u8],
+    rgb_table: &[[u8; 3]],
+    offsets: Option<(i32, i32)>,
+) -> Result<()> {
+    if rgba {
+        save_rgba_png(outname, width, height, pixels, painted, rgb_table, offsets)
+    } else {
+        save_indexed_png(outname, width, height, pixels, palette, offsets)
+    }
+}
 
-        let mut embiggened = vec![0; width as usize * height as usize];
-        let srcwidth = header.width as usize;
-        let srcheight = header.height as usize;
-        let dstwidth = width as usize;
-        for y in 0..srcheight {
-            for x in 0..srcwidth {
-                let src = pixels[x + y * srcwidth];
-                for dy in 0..6 {
-                    for dx in 0..5 {
-                        let dstx = x * 5 + dx;
-                        let dsty = y * 6 + dy;
-                        // trace!("({}, {}) -> ({}, {})", x, y, dstx, dsty);
-                        embiggened[dstx + dsty * dstwidth] = src;
-                    }
+// Dumb nearest-neighbor upscale at a 5:6 ratio to match the pixel aspect
+// ratio Doom ran on.
+fn upscale_5x6<T: Copy + Default>(src: &[T], srcwidth: usize, srcheight: usize) -> Vec<T> {
+    let dstwidth = srcwidth * 5;
+    let dstheight = srcheight * 6;
+    let mut dst = vec![T::default(); dstwidth * dstheight];
+    for y in 0..srcheight {
+        for x in 0..srcwidth {
+            let src = src[x + y * srcwidth];
+            for dy in 0..6 {
+                for dx in 0..5 {
+                    let dstx = x * 5 + dx;
+                    let dsty = y * 6 + dy;
+                    // trace!("({}, {}) -> ({}, {})", x, y, dstx, dsty);
+                    dst[dstx + dsty * dstwidth] = src;
                 }
             }
         }
-        pixels = embiggened;
     }
+    dst
+}
+
+// Source ports and editors like SLADE read the sprite's hotspot back from
+// this ancillary chunk, so round-tripping through `--rgba`/indexed PNGs
+// preserves it instead of silently dropping it on the floor.
+fn write_grab_chunk<W: Write>(
+    writer: &mut png::Writer<W>,
+    offsets: Option<(i32, i32)>,
+) -> Result<()> {
+    let Some((left_offset, top_offset)) = offsets else {
+        return Ok(());
+    };
+    let mut data = Vec::with_capacity(8);
+    data.extend_from_slice(&left_offset.to_be_bytes());
+    data.extend_from_slice(&top_offset.to_be_bytes());
+    writer.write_chunk(png::chunk::ChunkType(*b"grAb"), &data)?;
+    Ok(())
+}
+
+fn save_rgba_png(
+    outname: &str,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    painted: &[bool],
+    rgb_table: &[[u8; 3]],
+    offsets: Option<(i32, i32)>,
+) -> Result<()> {
+    let mut rgba = Vec::with_capacity(pixels.len() * 4);
+    for (&px, &is_painted) in pixels.iter().zip(painted) {
+        let [r, g, b] = rgb_table[px as usize];
+        rgba.extend_from_slice(&[r, g, b, if is_painted { 255 } else { 0 }]);
+    }
+
+    let mut encoder = png::Encoder::new(BufWriter::new(fs::File::create(outname)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
 
-    let mut encoder = png::Encoder::new(
-        BufWriter::new(fs::File::create(outname)?),
-        width,
-        height,
-    );
+    let mut writer = encoder.write_header()?;
+    write_grab_chunk(&mut writer, offsets)?;
+    writer.write_image_data(&rgba)?;
+
+    Ok(())
+}
+
+fn save_indexed_png(
+    outname: &str,
+    width: u32,
+    height: u32,
+    pixels: &[u8],
+    palette: &[u8],
+    offsets: Option<(i32, i32)>,
+) -> Result<()> {
+    let mut encoder = png::Encoder::new(BufWriter::new(fs::File::create(outname)?), width, height);
     encoder.set_color(png::ColorType::Indexed);
     encoder.set_depth(png::BitDepth::Eight);
     encoder.set_palette(palette);
@@ -262,7 +536,9 @@ fn save_sprite(
     }
     encoder.set_trns(&*TRNS);
 
-    encoder.write_header()?.write_image_data(&pixels)?;
+    let mut writer = encoder.write_header()?;
+    write_grab_chunk(&mut writer, offsets)?;
+    writer.write_image_data(pixels)?;
 
     Ok(())
 }
@@ -273,6 +549,274 @@ fn read_u8(c: &mut Cursor<&[u8]>) -> Result<u8> {
     Ok(buf[0])
 }
 
+// https://doomwiki.org/wiki/Sound
+#[derive(BinRead, Debug)]
+struct DmxHeader {
+    format: u16,
+    sample_rate: u16,
+    sample_count: u32,
+}
+
+// Every DMX sample buffer is padded with this many bytes of silence on
+// both ends.
+const DMX_PAD_BYTES: u64 = 16;
+
+#[derive(BinWrite, Debug)]
+struct WavHeader {
+    riff_magic: [u8; 4],
+    riff_size: u32,
+    wave_magic: [u8; 4],
+    fmt_magic: [u8; 4],
+    fmt_size: u32,
+    audio_format: u16,
+    num_channels: u16,
+    sample_rate: u32,
+    byte_rate: u32,
+    block_align: u16,
+    bits_per_sample: u16,
+    data_magic: [u8; 4],
+    data_size: u32,
+}
+
+fn save_sound(wad: &[u8], lump: &Filelump) -> Result<()> {
+    let base = lump.filepos as u64;
+    let mut c = Cursor::new(wad);
+    c.seek(SeekFrom::Start(base))?;
+    let header: DmxHeader = c.read_le()?;
+    trace!("    {header:?}");
+
+    if header.format != 3 {
+        warn!(
+            "{} has unrecognized DMX format {}; skipping",
+            lump.name(),
+            header.format
+        );
+        return Ok(());
+    }
+
+    let expected_len = 8 + 2 * DMX_PAD_BYTES + header.sample_count as u64;
+    if lump._size as u64 != expected_len {
+        warn!(
+            "{} doesn't match the expected DMX layout (lump is {} bytes, expected {expected_len}); skipping",
+            lump.name(),
+            lump._size,
+        );
+        return Ok(());
+    }
+
+    c.seek(SeekFrom::Current(DMX_PAD_BYTES as i64))?;
+    let mut samples = vec![0u8; header.sample_count as usize];
+    c.read_exact(&mut samples)?;
+
+    let data_size = samples.len() as u32;
+    let wav = WavHeader {
+        riff_magic: *b"RIFF",
+        riff_size: 36 + data_size,
+        wave_magic: *b"WAVE",
+        fmt_magic: *b"fmt ",
+        fmt_size: 16,
+        audio_format: 1,
+        num_channels: 1,
+        sample_rate: header.sample_rate as u32,
+        byte_rate: header.sample_rate as u32,
+        block_align: 1,
+        bits_per_sample: 8,
+        data_magic: *b"data",
+        data_size,
+    };
+
+    let outname = lump.name().to_owned() + ".wav";
+    let mut out = BufWriter::new(fs::File::create(outname)?);
+    wav.write_le(&mut out)?;
+    out.write_all(&samples)?;
+
+    Ok(())
+}
+
+// https://doomwiki.org/wiki/Texture1
+#[derive(BinRead, Debug)]
+struct MaptexturePatch {
+    originx: i16,
+    originy: i16,
+    patch: u16,
+    _stepdir: u16,
+    _colormap: u16,
+}
+
+#[derive(BinRead, Debug)]
+struct Maptexture {
+    namebuf: [u8; 8],
+    _masked: u32,
+    width: u16,
+    height: u16,
+    _columndirectory: u32,
+    _patchcount: u16,
+    #[br(count = _patchcount)]
+    patches: Vec<MaptexturePatch>,
+}
+
+impl Maptexture {
+    fn name(&self) -> &str {
+        doomstr(&self.namebuf)
+    }
+}
+
+fn read_pnames(wad: &[u8], lump: &Filelump) -> Result<Vec<[u8; 8]>> {
+    let mut c = Cursor::new(wad);
+    c.seek(SeekFrom::Start(lump.filepos as u64))?;
+    let count: u32 = c.read_le()?;
+    let mut names = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name: [u8; 8] = c.read_le()?;
+        names.push(name);
+    }
+    Ok(names)
+}
+
+fn read_textures(wad: &[u8], lump: &Filelump) -> Result<Vec<Maptexture>> {
+    let base = lump.filepos as u64;
+    let mut c = Cursor::new(wad);
+    c.seek(SeekFrom::Start(base))?;
+    let numtextures: u32 = c.read_le()?;
+    let mut offsets = Vec::with_capacity(numtextures as usize);
+    for _ in 0..numtextures {
+        offsets.push(c.read_le::<u32>()?);
+    }
+    let mut textures = Vec::with_capacity(numtextures as usize);
+    for offset in offsets {
+        c.seek(SeekFrom::Start(base + offset as u64))?;
+        textures.push(c.read_le()?);
+    }
+    Ok(textures)
+}
+
+// Blit src onto dst, clipping anything that falls outside dst's bounds
+// and skipping unpainted source pixels. dst_painted is updated in lockstep
+// with dst so callers can tell composited background from real pixels,
+// the same distinction save_sprite tracks for individual patches.
+#[allow(clippy::too_many_arguments)]
+fn blit(
+    dst: &mut [u8],
+    dst_painted: &mut [bool],
+    dstwidth: usize,
+    dstheight: usize,
+    src: &[u8],
+    src_painted: &[bool],
+    srcwidth: usize,
+    srcheight: usize,
+    originx: i32,
+    originy: i32,
+) {
+    for sy in 0..srcheight {
+        let dy = originy + sy as i32;
+        if dy < 0 || dy as usize >= dstheight {
+            continue;
+        }
+        for sx in 0..srcwidth {
+            let dx = originx + sx as i32;
+            if dx < 0 || dx as usize >= dstwidth {
+                continue;
+            }
+            let i = sx + sy * srcwidth;
+            if src_painted[i] {
+                let j = dx as usize + dy as usize * dstwidth;
+                dst[j] = src[i];
+                dst_painted[j] = true;
+            }
+        }
+    }
+}
+
+fn save_texture(
+    wad: &[u8],
+    texture: &Maptexture,
+    pnames: &[[u8; 8]],
+    patches: &[Filelump],
+    palette: &[u8],
+) -> Result<[bool; 256]> {
+    let width = texture.width as usize;
+    let height = texture.height as usize;
+    let mut pixels = vec![TRANSPARENT; width * height];
+    let mut painted = vec![false; width * height];
+
+    for patch in &texture.patches {
+        let name = pnames
+            .get(patch.patch as usize)
+            .map(|n| doomstr(n))
+            .with_context(|| {
+                format!(
+                    "Patch index {} out of range in {}",
+                    patch.patch,
+                    texture.name()
+                )
+            })?;
+        let Some(plump) = patches.iter().find(|l| l.name() == name) else {
+            warn!("Couldn't find patch {name} for texture {}", texture.name());
+            continue;
+        };
+        let patch_decoded = decode_patch(wad, plump.filepos)?;
+        blit(
+            &mut pixels,
+            &mut painted,
+            width,
+            height,
+            &patch_decoded.pixels,
+            &patch_decoded.painted,
+            patch_decoded.width as usize,
+            patch_decoded.height as usize,
+            patch.originx as i32,
+            patch.originy as i32,
+        );
+    }
+
+    // Same bookkeeping as save_sprite: only count colors actually painted
+    // onto the canvas, since the rest is just the TRANSPARENT sentinel.
+    let mut used_colors = [false; 256];
+    for (&px, &is_painted) in pixels.iter().zip(&painted) {
+        if is_painted {
+            used_colors[px as usize] = true;
+        }
+    }
+
+    let outname = texture.name().to_owned() + ".png";
+    save_indexed_png(&outname, width as u32, height as u32, &pixels, palette, None)?;
+    Ok(used_colors)
+}
+
+fn save_textures(wad: &[u8], dir: &[Filelump], palette: &[u8]) -> Result<[bool; 256]> {
+    let mut used_colors = [false; 256];
+
+    let Some(pnames_lump) = dir.iter().find(|l| l.name() == "PNAMES") else {
+        debug!("No PNAMES lump; skipping composite textures");
+        return Ok(used_colors);
+    };
+    let pnames = read_pnames(wad, pnames_lump)?;
+
+    let patch_start = dir.iter().position(|l| l.name() == "P_START");
+    let patch_end = dir.iter().position(|l| l.name() == "P_END");
+    let (Some(patch_start), Some(patch_end)) = (patch_start, patch_end) else {
+        warn!("No P_START/P_END markers; skipping composite textures");
+        return Ok(used_colors);
+    };
+    let patches = &dir[patch_start + 1..patch_end];
+
+    for texture_lump_name in ["TEXTURE1", "TEXTURE2"] {
+        let Some(lump) = dir.iter().find(|l| l.name() == texture_lump_name) else {
+            continue;
+        };
+        info!("{texture_lump_name}:");
+        for texture in read_textures(wad, lump)? {
+            info!("  {}", texture.name());
+            let texture_colors = save_texture(wad, &texture, &pnames, patches, palette)?;
+            for (used, was_used) in used_colors.iter_mut().zip(texture_colors) {
+                *used |= was_used;
+            }
+        }
+    }
+
+    Ok(used_colors)
+}
+
 fn main() {
     let args = Args::parse();
     init_logger(&args);